@@ -4,7 +4,7 @@
 //  Created:
 //    28 Jan 2024, 13:18:39
 //  Last edited:
-//    28 Jan 2024, 13:35:46
+//    05 Feb 2024, 11:27:14
 //  Auto updated?
 //    Yes
 //
@@ -16,22 +16,59 @@ use std::collections::HashSet;
 
 use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error::{Diagnostic, Level};
+use quote::quote;
 use syn::spanned::Spanned;
-use syn::{Attribute, Data, DataStruct, DeriveInput, Ident};
+use syn::{Attribute, Data, DataStruct, DeriveInput, Ident, LitStr, Type};
 
 
 /***** HELPER FUNCTIONS *****/
 /// Parses the attribute input to `#[derive(PasswordAuth)]`-macro.
 ///
+/// Looks for a `#[auth(field = "...")]` attribute. Multiple `field = "..."`-pairs may be given (either in one attribute or spread over several), in which case a field matching _any_ of them is accepted.
+///
 /// # Arguments
 /// - `attrs`: A list of attributes given to us by [`syn`].
 ///
 /// # Returns
-/// A set of fields to look out for, or else [`None`] if the `#[auth(field = ...)]` attribute was not given.
+/// A set of fields to look out for, or else [`None`] if no `#[auth(field = ...)]` attribute was given.
 ///
 /// # Errors
 /// This function may error if we found the attribute, but the user gave us shit input.
-fn parse_attrs(attrs: Vec<Attribute>) -> Result<Option<HashSet<String>>, Diagnostic> { Ok(None) }
+fn parse_attrs(attrs: Vec<Attribute>) -> Result<Option<HashSet<String>>, Diagnostic> {
+    let mut fields: HashSet<String> = HashSet::new();
+    for attr in attrs {
+        if !attr.path().is_ident("auth") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("field") {
+                let name: LitStr = meta.value()?.parse()?;
+                fields.insert(name.value());
+                Ok(())
+            } else {
+                Err(meta.error("Unknown key in `#[auth(...)]`-attribute; expected `field`"))
+            }
+        })
+        .map_err(|err| Diagnostic::spanned(attr.span(), Level::Error, format!("Failed to parse `#[auth(...)]`-attribute: {err}")))?;
+    }
+
+    if fields.is_empty() { Ok(None) } else { Ok(Some(fields)) }
+}
+
+/// Checks whether the given type is (syntactically) `String`.
+///
+/// # Arguments
+/// - `ty`: The [`Type`] to check.
+///
+/// # Returns
+/// True if `ty` is `String`, or false otherwise.
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.path.segments.last().map(|segment| segment.ident == "String").unwrap_or(false),
+        _ => false,
+    }
+}
 
 
 
@@ -47,7 +84,7 @@ pub fn derive(tokens: TokenStream2) -> Result<TokenStream2, Diagnostic> {
     // See if we parse what we think we parse (which is structs only; sorry enums/unions)
     let input: DeriveInput = match syn::parse2(tokens) {
         Ok(input) => input,
-        Err(err) => return Err(Diagnostic::spanned(tokens.span(), Level::Error, format!("{err}"))),
+        Err(err) => return Err(Diagnostic::spanned(err.span(), Level::Error, format!("{err}"))),
     };
     let data: DataStruct = match input.data {
         Data::Struct(s) => s,
@@ -64,10 +101,40 @@ pub fn derive(tokens: TokenStream2) -> Result<TokenStream2, Diagnostic> {
         if let Some(ident) = field.ident {
             if fieldnames.contains(&ident.to_string()) {
                 if password_field.is_some() {
-                    return Err(Diagnostic::spanned(ident.span(), Level::Error, "Found mu"));
+                    return Err(Diagnostic::spanned(
+                        ident.span(),
+                        Level::Error,
+                        format!("Found multiple candidate password fields (expected exactly one matching {fieldnames:?})"),
+                    ));
+                }
+                if !is_string(&field.ty) {
+                    return Err(Diagnostic::spanned(field.ty.span(), Level::Error, "Password field must be of type `String`".into()));
                 }
+                password_field = Some(ident);
             }
         }
     }
-    Ok(9)
+    let password_field: Ident = match password_field {
+        Some(ident) => ident,
+        None => {
+            return Err(Diagnostic::spanned(
+                input.ident.span(),
+                Level::Error,
+                format!("No field found matching {fieldnames:?}; specify one explicitly with `#[auth(field = \"...\")]`"),
+            ));
+        },
+    };
+
+    // Generate the impl
+    let ident: Ident = input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::auth::password::PasswordAuth for #ident #ty_generics #where_clause {
+            #[inline]
+            fn password(&self) -> &str { self.#password_field.as_str() }
+
+            #[inline]
+            fn password_mut(&mut self) -> &mut String { &mut self.#password_field }
+        }
+    })
 }
@@ -4,7 +4,7 @@
 //  Created:
 //    02 Jan 2024, 13:38:40
 //  Last edited:
-//    28 Jan 2024, 12:36:08
+//    06 Feb 2024, 10:27:15
 //  Auto updated?
 //    Yes
 //
@@ -16,7 +16,8 @@
 //
 
 // Declare modules
-// pub mod login;
-// pub mod manage;
-// pub mod spec;
+pub mod login;
+pub mod manage;
+pub mod providers;
+pub mod spec;
 pub mod password;
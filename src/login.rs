@@ -0,0 +1,152 @@
+//  LOGIN.rs
+//    by Lut99
+//
+//  Created:
+//    29 Jan 2024, 10:03:21
+//  Last edited:
+//    06 Feb 2024, 11:18:40
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Handles user login for the API.
+//
+
+use std::error;
+use std::fmt::{Display, Formatter, Result as FResult};
+
+use error_trace::ErrorTrace as _;
+use log::{error, info};
+use warp::hyper::{Body, StatusCode};
+use warp::reject::Rejection;
+use warp::reply::Response;
+
+use crate::password::{self, HashScheme};
+use crate::spec::{AuthConnector, AuthContext, CredentialType, ErrorReply, UserInfo};
+
+
+/***** ERRORS *****/
+/// Defines errors originating from user login.
+#[derive(Debug)]
+enum Error {
+    /// The backend connector failed to fetch the user to check.
+    ConnectorFetchUser { user: String, err: Box<dyn error::Error> },
+    /// Failed to check the given password attempt.
+    PasswordCheck { err: password::Error },
+    /// The given username/password-combination was incorrect (or the user didn't exist at all).
+    InvalidCredentials,
+}
+impl Error {
+    /// Converts this error into an appropriate [`Response`].
+    ///
+    /// # Returns
+    /// A [`Response`] that can be send to the user.
+    fn into_response(self) -> Response {
+        use Error::*;
+        match &self {
+            ConnectorFetchUser { .. } | PasswordCheck { .. } => {
+                // Log the internal error first
+                error!("[{}] {}", StatusCode::INTERNAL_SERVER_ERROR.as_u16(), self.trace());
+
+                // Show the error in the thing
+                let mut res: Response = Response::new(
+                    serde_json::to_string(&ErrorReply { id: "internal-error".into(), message: "An internal error has occurred".into() })
+                        .unwrap()
+                        .into(),
+                );
+                *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+
+                // Alright done
+                res
+            },
+
+            InvalidCredentials => {
+                // Log the (non-internal) error first
+                error!("[{}] {}", StatusCode::UNAUTHORIZED.as_u16(), self.trace());
+
+                // Show the error in the thing. Note that we are deliberately vague here, as
+                // distinguishing "wrong password" from "no such user" would leak account
+                // existence to the client.
+                let mut res: Response = Response::new(
+                    serde_json::to_string(&ErrorReply { id: "invalid-credentials".into(), message: "Invalid username or password".into() })
+                        .unwrap()
+                        .into(),
+                );
+                *res.status_mut() = StatusCode::UNAUTHORIZED;
+
+                // Alright done
+                res
+            },
+        }
+    }
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            ConnectorFetchUser { user, .. } => write!(f, "Database connector failed to fetch user '{user}'"),
+            PasswordCheck { err } => write!(f, "Failed to check password: {err}"),
+            InvalidCredentials => write!(f, "Invalid username or password"),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            ConnectorFetchUser { err, .. } => Some(&**err),
+            PasswordCheck { err } => Some(err),
+            InvalidCredentials => None,
+        }
+    }
+}
+
+
+
+
+/***** LIBRARY *****/
+/// API path to log an existing user in.
+///
+/// # Generics
+/// - `U`: A [`UserInfo`]-capable type. Bound with a higher-ranked `for<'de> UserInfo<'de>` (rather than a free `'de` on this function) so the returned future doesn't carry an unconstrained lifetime parameter.
+///
+/// # Arguments
+/// - `context`: An [`AuthContext`] that can be used to access the database containing users, as well as a key for signing stuff.
+/// - `username`: The name of the user that attempts to log in.
+/// - `attempt`: The cleartext password given by the client.
+///
+/// # Returns
+/// A [`Response`] that encodes what the client should know.
+///
+/// Note that this function never [`Reject`]s, and as such stops propagation of filters.
+///
+/// # Timing
+/// This function always performs exactly one password verification against `context.hash_scheme()`, regardless of whether `username` refers to an existing user. This is done through [`password::check_constant_time()`], so that an attacker cannot learn whether an account exists by measuring how long a login attempt takes — which holds only because `hash_scheme()` reflects whatever [`HashScheme`] real users are actually stored under.
+pub async fn login<U: for<'de> UserInfo<'de>>(context: impl AuthContext<U>, username: String, attempt: String) -> Result<Response, Rejection> {
+    info!("Handling login attempt");
+
+    /* Step 1. Fetch the user (if they exist) */
+    let conn: &_ = context.auth_connector();
+    let user: Option<U> = match conn.fetch_user(&username) {
+        Ok(user) => user,
+        Err(err) => return Ok(Error::ConnectorFetchUser { user: username, err: Box::new(err) }.into_response()),
+    };
+
+    /* Step 2. Check the password in constant time w.r.t. whether the user exists */
+    // Read the hash out of the `Password` credential, the same storage `create()` writes to
+    // (see `crate::manage::create()`), so login stays in sync with however a user was created.
+    let stored: Option<&str> = user.as_ref().and_then(|user| user.credential(CredentialType::Password)).map(|cred| cred.value.as_str());
+    let scheme: HashScheme = context.hash_scheme();
+    let ok: bool = match password::check_constant_time(stored, attempt.as_bytes(), &scheme) {
+        Ok(ok) => ok,
+        Err(err) => return Ok(Error::PasswordCheck { err }.into_response()),
+    };
+    if !ok {
+        return Ok(Error::InvalidCredentials.into_response());
+    }
+
+
+
+    /* Step 3. Done; the user is logged in */
+    Ok(Response::new(Body::empty()))
+}
@@ -4,7 +4,7 @@
 //  Created:
 //    02 Jan 2024, 13:45:31
 //  Last edited:
-//    21 Jan 2024, 17:55:45
+//    06 Feb 2024, 11:25:02
 //  Auto updated?
 //    Yes
 //
@@ -16,16 +16,15 @@
 use std::error;
 use std::fmt::{Display, Formatter, Result as FResult};
 
-use argon2::password_hash::rand_core::OsRng;
-use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
-use argon2::Argon2;
+use chrono::Utc;
 use error_trace::ErrorTrace as _;
 use log::{debug, error, info};
 use warp::hyper::{Body, StatusCode};
 use warp::reject::Rejection;
 use warp::reply::Response;
 
-use crate::spec::{AuthConnector, AuthContext, ErrorReply, UserInfo};
+use crate::password::{self, HashScheme};
+use crate::spec::{AuthConnector, AuthContext, Credential, CredentialType, ErrorReply, UserInfo};
 
 
 /***** ERRORS *****/
@@ -34,10 +33,14 @@ use crate::spec::{AuthConnector, AuthContext, ErrorReply, UserInfo};
 enum Error {
     /// The backed connector failed to find if a user exists.
     ConnectorUserExists { user: String, err: Box<dyn error::Error> },
+    /// The backend connector failed to insert the new user.
+    ConnectorInsertUser { user: String, err: Box<dyn error::Error> },
     /// Failed to hash a password.
-    PasswordHash { err: argon2::password_hash::Error },
+    PasswordHash { err: password::Error },
     /// A user already exists in the database.
     UserExists { name: String },
+    /// The given user info did not carry a (cleartext) password credential to hash.
+    MissingPassword { name: String },
 }
 impl Error {
     /// Converts this error into an appropriate [`Response`].
@@ -47,7 +50,7 @@ impl Error {
     fn into_response(self) -> Response {
         use Error::*;
         match &self {
-            ConnectorUserExists { .. } | PasswordHash { .. } => {
+            ConnectorUserExists { .. } | ConnectorInsertUser { .. } | PasswordHash { .. } => {
                 // Log the internal error first
                 error!("[{}] {}", StatusCode::INTERNAL_SERVER_ERROR.as_u16(), self.trace());
 
@@ -81,6 +84,25 @@ impl Error {
                 // Alright done
                 res
             },
+
+            MissingPassword { name } => {
+                // Log the internal error first
+                error!("[{}] {}", StatusCode::BAD_REQUEST.as_u16(), self.trace());
+
+                // Show the error in the thing
+                let mut res: Response = Response::new(
+                    serde_json::to_string(&ErrorReply {
+                        id:      "missing-password".into(),
+                        message: format!("No password given for user '{name}'"),
+                    })
+                    .unwrap()
+                    .into(),
+                );
+                *res.status_mut() = StatusCode::BAD_REQUEST;
+
+                // Alright done
+                res
+            },
         }
     }
 }
@@ -89,8 +111,10 @@ impl Display for Error {
         use Error::*;
         match self {
             ConnectorUserExists { user, .. } => write!(f, "Database connector failed to check if user '{user}' exists"),
+            ConnectorInsertUser { user, .. } => write!(f, "Database connector failed to insert user '{user}'"),
             PasswordHash { err } => write!(f, "Failed to hash password: {err}"),
             UserExists { name } => write!(f, "User with name '{name}' already exists"),
+            MissingPassword { name } => write!(f, "No password given for user '{name}'"),
         }
     }
 }
@@ -99,8 +123,10 @@ impl error::Error for Error {
         use Error::*;
         match self {
             ConnectorUserExists { err, .. } => Some(&**err),
-            PasswordHash { err } => None,
+            ConnectorInsertUser { err, .. } => Some(&**err),
+            PasswordHash { err } => Some(err),
             UserExists { .. } => None,
+            MissingPassword { .. } => None,
         }
     }
 }
@@ -113,7 +139,7 @@ impl error::Error for Error {
 /// API path to create a new user.
 ///
 /// # Generics
-/// - `U`: A [`UserInfo`]-capable type that is read from the body to extract the information of the new user.
+/// - `U`: A [`UserInfo`]-capable type that is read from the body to extract the information of the new user. Bound with a higher-ranked `for<'de> UserInfo<'de>` (rather than a free `'de` on this function), mirroring [`login()`](crate::login::login), so the returned future doesn't carry an unconstrained lifetime parameter.
 ///
 /// # Arguments
 /// - `context`: An [`AuthContext`] that can be used to access the database containing users, as well as a key for signing stuff.
@@ -123,38 +149,46 @@ impl error::Error for Error {
 /// A [`Response`] that encodes what the client should know.
 ///
 /// Note that this function never [`Reject`]s, and as such stops propagation of filters.
-pub async fn create<'de, U: UserInfo<'de>>(context: impl AuthContext<U>, mut info: U) -> Result<Response, Rejection> {
+pub async fn create<U: for<'de> UserInfo<'de>>(context: impl AuthContext<U>, mut info: U) -> Result<Response, Rejection> {
     info!("Handling new user creation");
 
     /* Step 1. Check if user is unique */
     // Use the database connector for this
     let conn: &_ = context.auth_connector();
     match conn.user_exists(info.name()) {
-        Ok(true) => {},
-        Ok(false) => return Ok(Error::UserExists { name: info.name().into() }.into_response()),
+        Ok(true) => return Ok(Error::UserExists { name: info.name().into() }.into_response()),
+        Ok(false) => {},
         Err(err) => return Ok(Error::ConnectorUserExists { user: info.name().into(), err: Box::new(err) }.into_response()),
     }
 
 
 
     /* Step 2. Hash the password */
-    // Get the password & salt
-    let password: &[u8] = info.password().as_bytes();
-    let salt: SaltString = SaltString::generate(&mut OsRng);
-
-    // Prepare the hasher with default settings, then hash!
-    let argon2 = Argon2::default();
-    let hpassword: String = match argon2.hash_password(password, &salt) {
-        Ok(pwd) => pwd.to_string(),
+    // Get the cleartext password credential
+    let password: &[u8] = match info.credential(CredentialType::Password) {
+        Some(cred) => cred.value.as_bytes(),
+        None => return Ok(Error::MissingPassword { name: info.name().into() }.into_response()),
+    };
+
+    // Hash it with whichever `HashScheme` this context is configured for
+    let scheme: HashScheme = context.hash_scheme();
+    let hpassword: String = match scheme.hash(password) {
+        Ok(hash) => hash,
         Err(err) => return Ok(Error::PasswordHash { err }.into_response()),
     };
 
-    // Update the password in the to-be-stored struct
-    info.update_password(hpassword);
+    // Update the password credential in the to-be-stored struct
+    let now = Utc::now();
+    info.set_credential(Credential { kind: CredentialType::Password, value: hpassword, validated: false, time_created: now, last_updated: now });
 
 
 
     /* Step 3. Insert into DB and return */
+    let name: String = info.name().into();
+    if let Err(err) = conn.insert_user(info) {
+        return Ok(Error::ConnectorInsertUser { user: name, err: Box::new(err) }.into_response());
+    }
+
     // Done
-    Ok(())
+    Ok(Response::new(Body::empty()))
 }
@@ -4,7 +4,7 @@
 //  Created:
 //    28 Jan 2024, 12:37:13
 //  Last edited:
-//    28 Jan 2024, 13:09:30
+//    06 Feb 2024, 11:08:51
 //  Auto updated?
 //    Yes
 //
@@ -15,10 +15,14 @@
 
 use std::error;
 use std::fmt::{Display, Formatter, Result as FResult};
+use std::sync::{Mutex, OnceLock, PoisonError};
 
 use argon2::password_hash::rand_core::OsRng;
-use argon2::password_hash::{PasswordHasher as _, SaltString};
-use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use argon2::password_hash::{PasswordHasher as _, PasswordVerifier as _, SaltString};
+use argon2::{Algorithm as Argon2Algorithm, Argon2, Params as Argon2Params, Version as Argon2Version};
+use scrypt::{Params as ScryptParams, Scrypt};
+
+pub use argon2::password_hash::PasswordHash;
 
 
 /***** ERRORS *****/
@@ -33,14 +37,23 @@ pub enum Error {
     HashParse { err: argon2::password_hash::Error },
     /// Failed to verify the given password against the internal hash.
     HashVerify { err: argon2::password_hash::Error },
+    /// The internal hash was written by an algorithm we don't know how to verify.
+    UnknownAlgorithm { algorithm: String },
+    /// The given cost parameters are not valid for Argon2.
+    InvalidArgon2Params { err: argon2::Error },
+    /// The given cost parameters are not valid for scrypt.
+    InvalidScryptParams { err: scrypt::errors::InvalidParams },
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
         use Error::*;
         match self {
-            HashCompute { .. } => write!(f, "Failed to compute Argon2 hash"),
-            HashParse { .. } => write!(f, "Failed to parse internal Argon2 password hash"),
-            HashVerify { .. } => write!(f, "Failed to verify given attempt against internal Argon2 password hash"),
+            HashCompute { .. } => write!(f, "Failed to compute password hash"),
+            HashParse { .. } => write!(f, "Failed to parse internal password hash"),
+            HashVerify { .. } => write!(f, "Failed to verify given attempt against internal password hash"),
+            UnknownAlgorithm { algorithm } => write!(f, "Internal password hash uses unknown algorithm '{algorithm}'"),
+            InvalidArgon2Params { .. } => write!(f, "Given Argon2 cost parameters are invalid"),
+            InvalidScryptParams { .. } => write!(f, "Given scrypt cost parameters are invalid"),
         }
     }
 }
@@ -51,15 +64,376 @@ impl error::Error for Error {
             HashCompute { err } => Some(err),
             HashParse { err } => Some(err),
             HashVerify { err } => Some(err),
+            UnknownAlgorithm { .. } => None,
+            InvalidArgon2Params { err } => Some(err),
+            InvalidScryptParams { err } => Some(err),
+        }
+    }
+}
+
+
+
+
+
+/***** CONSTANTS *****/
+/// A fixed password that is never assigned to any real user.
+///
+/// This is hashed once per [`HashScheme`] (see [`dummy_hash()`]) and then verified against whenever there is no real password hash to compare an attempt to, so that doing so costs the same as a genuine [`PasswordAuthExt::check_password()`]-call against a hash produced by that same scheme.
+const DUMMY_PASSWORD: &str = "correct-horse-battery-staple-but-never-actually-used-by-anyone";
+
+/// Identifies a [`HashScheme`]'s algorithm and cost parameters, without requiring the underlying `argon2`/`scrypt` parameter types to implement [`PartialEq`]/[`Eq`] themselves.
+///
+/// Used purely as a cache key in [`dummy_hash()`], to tell whether a previously-cached dummy hash was computed with the same scheme that's being requested now.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum SchemeKey {
+    Argon2id { m_cost: u32, t_cost: u32, p_cost: u32 },
+    Scrypt { log_n: u8, r: u32, p: u32 },
+}
+impl SchemeKey {
+    /// Extracts the [`SchemeKey`] of a given [`HashScheme`].
+    fn of(scheme: &HashScheme) -> Self {
+        match scheme {
+            HashScheme::Argon2id(params) => Self::Argon2id { m_cost: params.m_cost(), t_cost: params.t_cost(), p_cost: params.p_cost() },
+            HashScheme::Scrypt(params) => Self::Scrypt { log_n: params.log_n(), r: params.r(), p: params.p() },
         }
     }
 }
 
+/// Returns a lazily-computed hash of [`DUMMY_PASSWORD`], produced by the given `scheme`.
+///
+/// The hash is only recomputed when `scheme` differs from whichever scheme it was last computed for (the common case is a single, unchanging deployment-wide default, so in practice this still amounts to "once"). This is used by [`PasswordAuthExt::check_password_constant_time()`] to pay the same algorithm-and-cost cost on the "user does not exist" path as on the "user exists, wrong password" path would pay for a hash produced by `scheme` — which matters because real users may be stored under a different [`HashScheme`] (e.g., during an algorithm migration, or with tuned cost parameters) than the crate's bare defaults.
+///
+/// # Arguments
+/// - `scheme`: The [`HashScheme`] that real users are (or would be) hashed with.
+///
+/// # Returns
+/// The PHC hash string of [`DUMMY_PASSWORD`], hashed with `scheme`.
+///
+/// # Errors
+/// This function may error if `scheme` failed to hash [`DUMMY_PASSWORD`].
+fn dummy_hash(scheme: &HashScheme) -> Result<String, Error> {
+    static CACHE: OnceLock<Mutex<Option<(SchemeKey, String)>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(None));
+    let key: SchemeKey = SchemeKey::of(scheme);
+
+    let mut guard = cache.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((cached_key, hash)) = guard.as_ref() {
+        if *cached_key == key {
+            return Ok(hash.clone());
+        }
+    }
+
+    let hash: String = scheme.hash(DUMMY_PASSWORD.as_bytes())?;
+    *guard = Some((key, hash.clone()));
+    Ok(hash)
+}
+
+/// Checks a password attempt against a stored PHC hash that may or may not be present, in constant time w.r.t. whether it's present.
+///
+/// This is the callee shared by [`PasswordAuthExt::check_password_constant_time_with()`] and anything else (e.g. [`login()`](crate::login::login)) that needs to check a stored hash that isn't reached through a [`PasswordAuth`]-implementor — e.g. because it lives in a [`Credential`](crate::spec::Credential) instead.
+///
+/// # Arguments
+/// - `stored`: The PHC hash string to check against, or [`None`] if no such hash exists.
+/// - `attempt`: The cleartext password to verify.
+/// - `scheme`: The [`HashScheme`] that real users are stored under, used to compute the dummy hash when `stored` is [`None`].
+///
+/// # Returns
+/// True if `attempt` matches `stored`, or false otherwise (including when `stored` is [`None`]).
+///
+/// # Errors
+/// This function may error if it failed to parse or verify against `stored`, or to compute the dummy hash.
+pub(crate) fn check_constant_time(stored: Option<&str>, attempt: impl AsRef<[u8]>, scheme: &HashScheme) -> Result<bool, Error> {
+    match stored {
+        Some(stored) => verify(stored, attempt.as_ref()),
+        None => {
+            // Pay the cost of a real verification, but never accept the dummy password itself
+            verify(&dummy_hash(scheme)?, attempt.as_ref())?;
+            Ok(false)
+        },
+    }
+}
+
+
+
+
+
+/***** HELPER FUNCTIONS *****/
+/// Verifies an `attempt` against a `stored` PHC hash string, detecting which algorithm produced `stored` instead of assuming Argon2.
+///
+/// This lets a database that holds a mix of algorithms (e.g., because it's mid-migration between [`HashScheme`]s) verify every row correctly.
+///
+/// # Arguments
+/// - `stored`: The PHC hash string to verify `attempt` against.
+/// - `attempt`: The cleartext password to verify.
+///
+/// # Returns
+/// True if `attempt` matches `stored`, or false otherwise.
+///
+/// # Errors
+/// This function may error if `stored` could not be parsed, if its algorithm is not one we know how to verify, or if the verification itself failed for some other reason.
+fn verify(stored: &str, attempt: &[u8]) -> Result<bool, Error> {
+    let hash: PasswordHash = match PasswordHash::new(stored) {
+        Ok(hash) => hash,
+        Err(err) => return Err(Error::HashParse { err }),
+    };
+
+    // Dispatch to the verifier matching the algorithm identifier embedded in the hash
+    let res = match hash.algorithm.as_str() {
+        "argon2id" | "argon2i" | "argon2d" => Argon2::default().verify_password(attempt, &hash),
+        "scrypt" => Scrypt.verify_password(attempt, &hash),
+        algorithm => return Err(Error::UnknownAlgorithm { algorithm: algorithm.into() }),
+    };
+    match res {
+        Ok(_) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(err) => Err(Error::HashVerify { err }),
+    }
+}
+
+/// Checks whether a `stored` PHC hash string is weaker than the cost configured by `target`, and so should be rehashed.
+///
+/// A hash is considered weaker if it was produced by a different algorithm than `target` (e.g., the deployment is migrating from scrypt to Argon2id), or if it was produced by the same algorithm with at least one cost parameter lower than `target`'s and none higher. A hash that is lower in one parameter but higher in another is left as-is, since rehashing it with `target` would lower the latter. This function never reports a hash as needing an upgrade if it already meets or exceeds `target`'s cost, so callers can never accidentally downgrade a user's hash.
+///
+/// # Arguments
+/// - `stored`: The PHC hash string to inspect.
+/// - `target`: The [`HashScheme`] that represents the currently configured (desired) cost.
+///
+/// # Returns
+/// True if `stored` should be rehashed with `target` to meet the desired cost, or false if it already does.
+///
+/// # Errors
+/// This function may error if `stored` could not be parsed, or if its embedded parameters could not be interpreted as parameters of its own algorithm.
+fn needs_upgrade(stored: &str, target: &HashScheme) -> Result<bool, Error> {
+    let hash: PasswordHash = match PasswordHash::new(stored) {
+        Ok(hash) => hash,
+        Err(err) => return Err(Error::HashParse { err }),
+    };
+
+    Ok(match target {
+        HashScheme::Argon2id(target_params) => {
+            if hash.algorithm.as_str() != "argon2id" {
+                true
+            } else {
+                let stored_params: Argon2Params = match Argon2Params::try_from(&hash) {
+                    Ok(params) => params,
+                    Err(err) => return Err(Error::HashParse { err }),
+                };
+                // Only an upgrade if every parameter of `target` is at least as strong as `stored`'s;
+                // otherwise rehashing with `target` would lower whichever parameter is weaker there.
+                stored_params.m_cost() <= target_params.m_cost()
+                    && stored_params.t_cost() <= target_params.t_cost()
+                    && stored_params.p_cost() <= target_params.p_cost()
+                    && (stored_params.m_cost() < target_params.m_cost()
+                        || stored_params.t_cost() < target_params.t_cost()
+                        || stored_params.p_cost() < target_params.p_cost())
+            }
+        },
 
+        HashScheme::Scrypt(target_params) => {
+            if hash.algorithm.as_str() != "scrypt" {
+                true
+            } else {
+                let stored_params: ScryptParams = match ScryptParams::try_from(&hash) {
+                    Ok(params) => params,
+                    Err(err) => return Err(Error::HashParse { err }),
+                };
+                // Same reasoning as the Argon2id case: never rehash if that would lower any parameter.
+                stored_params.log_n() <= target_params.log_n()
+                    && stored_params.r() <= target_params.r()
+                    && stored_params.p() <= target_params.p()
+                    && (stored_params.log_n() < target_params.log_n() || stored_params.r() < target_params.r() || stored_params.p() < target_params.p())
+            }
+        },
+    })
+}
 
 
 
 /***** LIBRARY *****/
+/// Selects which algorithm & cost parameters are used to hash and verify passwords.
+///
+/// Built through [`HashScheme::argon2id()`] or [`HashScheme::scrypt()`], which return a builder for configuring the algorithm's cost parameters. Defaults ([`HashScheme::default()`]) to Argon2id with the [`argon2`]-crate's recommended parameters.
+///
+/// Verification does not use a [`HashScheme`] at all (see [`verify()`]), since the algorithm and its parameters are always read back from the stored PHC hash string itself; a [`HashScheme`] only governs newly-computed hashes.
+#[derive(Clone, Debug)]
+pub enum HashScheme {
+    /// Hash using Argon2id.
+    Argon2id(Argon2Params),
+    /// Hash using scrypt.
+    Scrypt(ScryptParams),
+}
+impl HashScheme {
+    /// Starts building a [`HashScheme`] that hashes using Argon2id.
+    ///
+    /// # Returns
+    /// A [`Argon2Builder`] with the `argon2`-crate's recommended defaults, ready to be tuned and [`build()`](Argon2Builder::build())-ed.
+    #[inline]
+    pub fn argon2id() -> Argon2Builder { Argon2Builder::default() }
+
+    /// Starts building a [`HashScheme`] that hashes using scrypt.
+    ///
+    /// # Returns
+    /// A [`ScryptBuilder`] with the `scrypt`-crate's recommended defaults, ready to be tuned and [`build()`](ScryptBuilder::build())-ed.
+    #[inline]
+    pub fn scrypt() -> ScryptBuilder { ScryptBuilder::default() }
+
+    /// Hashes the given password according to this scheme.
+    ///
+    /// # Arguments
+    /// - `password`: The cleartext password to hash.
+    ///
+    /// # Returns
+    /// A PHC hash string encoding the algorithm, its parameters, a freshly generated salt and the resulting hash.
+    ///
+    /// # Errors
+    /// This function may error if the underlying algorithm failed to compute the hash.
+    pub(crate) fn hash(&self, password: &[u8]) -> Result<String, Error> {
+        let salt: SaltString = SaltString::generate(&mut OsRng);
+        match self {
+            HashScheme::Argon2id(params) => Argon2::new(Argon2Algorithm::Argon2id, Argon2Version::V0x13, params.clone())
+                .hash_password(password, &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|err| Error::HashCompute { err }),
+            HashScheme::Scrypt(params) => {
+                Scrypt.hash_password_customized(password, None, None, *params, &salt).map(|hash| hash.to_string()).map_err(|err| Error::HashCompute { err })
+            },
+        }
+    }
+}
+impl Default for HashScheme {
+    /// Defaults to Argon2id with the `argon2`-crate's recommended cost parameters.
+    #[inline]
+    fn default() -> Self { HashScheme::Argon2id(Argon2Params::default()) }
+}
+
+/// Builder for a [`HashScheme::Argon2id`], letting deployments tune the work factor without patching the crate.
+///
+/// Defaults to the `argon2`-crate's recommended parameters. Construct through [`HashScheme::argon2id()`].
+#[derive(Clone, Debug)]
+pub struct Argon2Builder {
+    /// Memory cost, in KiB.
+    m_cost: u32,
+    /// Time cost (i.e., number of iterations).
+    t_cost: u32,
+    /// Degree of parallelism.
+    p_cost: u32,
+}
+impl Argon2Builder {
+    /// Sets the memory cost, in KiB.
+    #[inline]
+    pub fn memory_cost(mut self, m_cost: u32) -> Self {
+        self.m_cost = m_cost;
+        self
+    }
+
+    /// Sets the time cost (i.e., number of iterations).
+    #[inline]
+    pub fn time_cost(mut self, t_cost: u32) -> Self {
+        self.t_cost = t_cost;
+        self
+    }
+
+    /// Sets the degree of parallelism.
+    #[inline]
+    pub fn parallelism(mut self, p_cost: u32) -> Self {
+        self.p_cost = p_cost;
+        self
+    }
+
+    /// Finalizes this builder into a [`HashScheme`].
+    ///
+    /// # Errors
+    /// This function may error if the configured cost parameters are not valid Argon2 parameters.
+    pub fn build(self) -> Result<HashScheme, Error> {
+        match Argon2Params::new(self.m_cost, self.t_cost, self.p_cost, None) {
+            Ok(params) => Ok(HashScheme::Argon2id(params)),
+            Err(err) => Err(Error::InvalidArgon2Params { err }),
+        }
+    }
+}
+impl Default for Argon2Builder {
+    #[inline]
+    fn default() -> Self {
+        let defaults = Argon2Params::default();
+        Self { m_cost: defaults.m_cost(), t_cost: defaults.t_cost(), p_cost: defaults.p_cost() }
+    }
+}
+
+/// Builder for a [`HashScheme::Scrypt`], letting deployments tune the work factor without patching the crate.
+///
+/// Defaults to the `scrypt`-crate's recommended parameters. Construct through [`HashScheme::scrypt()`].
+#[derive(Clone, Debug)]
+pub struct ScryptBuilder {
+    /// The log2 of the CPU/memory cost parameter.
+    log_n: u8,
+    /// The block size parameter.
+    r: u32,
+    /// The parallelization parameter.
+    p: u32,
+}
+impl ScryptBuilder {
+    /// Sets the log2 of the CPU/memory cost parameter.
+    #[inline]
+    pub fn log_n(mut self, log_n: u8) -> Self {
+        self.log_n = log_n;
+        self
+    }
+
+    /// Sets the block size parameter.
+    #[inline]
+    pub fn r(mut self, r: u32) -> Self {
+        self.r = r;
+        self
+    }
+
+    /// Sets the parallelization parameter.
+    #[inline]
+    pub fn p(mut self, p: u32) -> Self {
+        self.p = p;
+        self
+    }
+
+    /// Finalizes this builder into a [`HashScheme`].
+    ///
+    /// # Errors
+    /// This function may error if the configured cost parameters are not valid scrypt parameters.
+    pub fn build(self) -> Result<HashScheme, Error> {
+        match ScryptParams::new(self.log_n, self.r, self.p, ScryptParams::RECOMMENDED_LEN) {
+            Ok(params) => Ok(HashScheme::Scrypt(params)),
+            Err(err) => Err(Error::InvalidScryptParams { err }),
+        }
+    }
+}
+impl Default for ScryptBuilder {
+    #[inline]
+    fn default() -> Self {
+        let defaults = ScryptParams::recommended();
+        Self { log_n: defaults.log_n(), r: defaults.r(), p: defaults.p() }
+    }
+}
+
+/// Outcome of [`PasswordAuthExt::check_and_upgrade()`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CheckOutcome {
+    /// The attempt did not match the stored password.
+    Mismatch,
+    /// The attempt matched, and the stored hash already met the target cost.
+    Matched,
+    /// The attempt matched, and the stored hash was weaker than the target cost, so it has been rehashed and written back.
+    Upgraded,
+}
+impl CheckOutcome {
+    /// Returns whether the attempt matched the stored password (i.e., this is [`Self::Matched`] or [`Self::Upgraded`]).
+    #[inline]
+    pub fn matched(&self) -> bool { !matches!(self, Self::Mismatch) }
+
+    /// Returns whether the stored password was rehashed as part of the check.
+    #[inline]
+    pub fn upgraded(&self) -> bool { matches!(self, Self::Upgraded) }
+}
+
+
+
 /// Base trait that, when implemented, unlocks the password features of the [`PasswordAuthExt`] trait.
 ///
 /// See that trait for a more practical example.
@@ -135,32 +509,31 @@ pub trait PasswordAuth {
 /// assert_eq!(user.check_password("hacked".as_bytes()).unwrap(), false);
 /// ```
 pub trait PasswordAuthExt: PasswordAuth {
-    /// Updates the internal password with its hashed counterpart.
+    /// Updates the internal password with its hashed counterpart, using [`HashScheme::default()`].
     ///
-    /// The [`argon2`] crate is used for the hashing the password returned by (and updated by) the [`PasswordAuth`] implementation.
+    /// See [`Self::hash_password_with()`] if you want to pick a specific algorithm or tune its cost parameters.
     ///
     /// # Errors
-    /// This function may error if the [`Argon2::hash_password()`] function on which it relies errors.
-    fn hash_password(&mut self) -> Result<(), Error> {
-        // Get the password & salt
-        let password: &[u8] = self.password().as_bytes();
-        let salt: SaltString = SaltString::generate(&mut OsRng);
-
-        // Prepare the hasher with default settings, then hash!
-        let argon2 = Argon2::default();
-        let hpassword: String = match argon2.hash_password(password, &salt) {
-            Ok(pwd) => pwd.to_string(),
-            Err(err) => return Err(Error::HashCompute { err }),
-        };
+    /// This function may error if the underlying hashing algorithm fails to compute the hash.
+    #[inline]
+    fn hash_password(&mut self) -> Result<(), Error> { self.hash_password_with(&HashScheme::default()) }
 
-        // Alright that's it, update and done
+    /// Updates the internal password with its hashed counterpart, computed using the given [`HashScheme`].
+    ///
+    /// # Arguments
+    /// - `scheme`: The [`HashScheme`] (algorithm + cost parameters) to hash the password with.
+    ///
+    /// # Errors
+    /// This function may error if the underlying hashing algorithm fails to compute the hash.
+    fn hash_password_with(&mut self, scheme: &HashScheme) -> Result<(), Error> {
+        let hpassword: String = scheme.hash(self.password().as_bytes())?;
         *self.password_mut() = hpassword;
         Ok(())
     }
 
     /// Checks the internal password with a given attempt.
     ///
-    /// The [`argon2`] crate is used for this, and the password is retrieved using the [`PasswordAuth::password_bytes()`]-implementation.
+    /// The algorithm used to verify is detected from the PHC identifier (e.g. `$argon2id$`, `$scrypt$`) embedded in the internal hash itself, not assumed to be Argon2. This means a database holding a mix of algorithms (e.g., mid-migration between [`HashScheme`]s) verifies correctly regardless of which algorithm produced any given row.
     ///
     /// # Arguments
     /// - `attempt`: The password to verify.
@@ -169,23 +542,99 @@ pub trait PasswordAuthExt: PasswordAuth {
     /// A boolean indicating if the password matched (true) or not (false).
     ///
     /// # Errors
+    /// This function may error if it failed to either parse the internal hash, if its algorithm is unknown to us, or if it failed to verify the given attempt against it.
+    #[inline]
+    fn check_password(&self, attempt: impl AsRef<[u8]>) -> Result<bool, Error> { verify(self.password(), attempt.as_ref()) }
+
+    /// Checks a password attempt against a user that may or may not exist, in constant time, using [`HashScheme::default()`] for the dummy hash when `user` is [`None`].
+    ///
+    /// See [`Self::check_password_constant_time_with()`] if users may be stored under a non-default [`HashScheme`] (e.g., tuned cost parameters, or a deployment mid-migration between algorithms) — this default-only variant otherwise reintroduces the very timing oracle it's meant to close.
+    ///
+    /// # Arguments
+    /// - `user`: The [`PasswordAuth`]-implementor to check the password of, or [`None`] if no such user exists.
+    /// - `attempt`: The password to verify.
+    ///
+    /// # Returns
+    /// A boolean indicating if the password matched (true) or not (false). Always `false` if `user` is [`None`].
+    ///
+    /// # Errors
     /// This function may error if it failed to either parse the internal hash or verify the given one against it.
-    fn check_password(&self, attempt: impl AsRef<[u8]>) -> Result<bool, Error> {
+    #[inline]
+    fn check_password_constant_time(user: Option<&Self>, attempt: impl AsRef<[u8]>) -> Result<bool, Error>
+    where
+        Self: Sized,
+    {
+        Self::check_password_constant_time_with(user, attempt, &HashScheme::default())
+    }
+
+    /// Checks a password attempt against a user that may or may not exist, in constant time with respect to `scheme`.
+    ///
+    /// This is the timing-attack-resistant counterpart to [`Self::check_password()`]. If `user` is [`Some`], it behaves exactly like `user.check_password(attempt)`. If `user` is [`None`] (e.g., because no account with the attempted username exists), it still runs a full hash verification against a fixed, lazily-computed dummy hash (see [`dummy_hash()`]) before returning `false`.
+    ///
+    /// Because both branches perform exactly one verification with the same cost parameters, an attacker cannot distinguish "user exists, wrong password" from "user does not exist" by measuring response latency — but only if `scheme` matches whatever [`HashScheme`] real users are actually stored under. Pass that scheme here explicitly rather than relying on the crate's bare defaults.
+    ///
+    /// # Arguments
+    /// - `user`: The [`PasswordAuth`]-implementor to check the password of, or [`None`] if no such user exists.
+    /// - `attempt`: The password to verify.
+    /// - `scheme`: The [`HashScheme`] that real users are stored under, used to compute the dummy hash on the `user.is_none()` path.
+    ///
+    /// # Returns
+    /// A boolean indicating if the password matched (true) or not (false). Always `false` if `user` is [`None`].
+    ///
+    /// # Errors
+    /// This function may error if it failed to either parse the internal hash, verify the given one against it, or compute the dummy hash.
+    fn check_password_constant_time_with(user: Option<&Self>, attempt: impl AsRef<[u8]>, scheme: &HashScheme) -> Result<bool, Error>
+    where
+        Self: Sized,
+    {
+        check_constant_time(user.map(PasswordAuth::password), attempt, scheme)
+    }
+
+    /// Checks a password attempt and, on success, transparently rehashes it if its cost is weaker than [`HashScheme::default()`].
+    ///
+    /// See [`Self::check_and_upgrade_with()`] to target a specific [`HashScheme`] instead of the default.
+    ///
+    /// # Arguments
+    /// - `attempt`: The password to verify (and, on a weak match, rehash).
+    ///
+    /// # Returns
+    /// A [`CheckOutcome`] describing whether the attempt matched, and whether it was upgraded.
+    ///
+    /// # Errors
+    /// This function may error if it failed to verify the attempt, or to rehash it on an upgrade.
+    #[inline]
+    fn check_and_upgrade(&mut self, attempt: impl AsRef<[u8]>) -> Result<CheckOutcome, Error> { self.check_and_upgrade_with(attempt, &HashScheme::default()) }
+
+    /// Checks a password attempt and, on success, transparently rehashes it if its cost is weaker than `target`.
+    ///
+    /// This lets operators raise work factors over time (or migrate between algorithms) and have users' stored hashes silently strengthen on their next successful login. The stored hash is only ever rehashed on a verified match, and its parameters are never downgraded: if it already meets or exceeds `target`'s cost, this is a no-op.
+    ///
+    /// # Arguments
+    /// - `attempt`: The password to verify (and, on a weak match, rehash).
+    /// - `target`: The [`HashScheme`] that represents the currently configured (desired) cost.
+    ///
+    /// # Returns
+    /// A [`CheckOutcome`] describing whether the attempt matched, and whether it was upgraded.
+    ///
+    /// # Errors
+    /// This function may error if it failed to verify the attempt, to inspect its cost parameters, or to rehash it on an upgrade.
+    fn check_and_upgrade_with(&mut self, attempt: impl AsRef<[u8]>, target: &HashScheme) -> Result<CheckOutcome, Error> {
         let attempt: &[u8] = attempt.as_ref();
 
-        // Create a [`PasswordHash`] out of the internal one.
-        let hash: PasswordHash = match PasswordHash::new(self.password()) {
-            Ok(hash) => hash,
-            Err(err) => return Err(Error::HashParse { err }),
-        };
-
-        // Compare the hashes
-        let argon2 = Argon2::default();
-        match argon2.verify_password(attempt, &hash) {
-            Ok(_) => Ok(true),
-            Err(argon2::password_hash::Error::Password) => Ok(false),
-            Err(err) => Err(Error::HashVerify { err }),
+        // Step 1. Verify the attempt; bail on a mismatch before touching anything
+        if !verify(self.password(), attempt)? {
+            return Ok(CheckOutcome::Mismatch);
         }
+
+        // Step 2. See if the stored hash is weaker than the target cost
+        if !needs_upgrade(self.password(), target)? {
+            return Ok(CheckOutcome::Matched);
+        }
+
+        // Step 3. It is; rehash the (now-verified) attempt with the target cost and write it back
+        let hpassword: String = target.hash(attempt)?;
+        *self.password_mut() = hpassword;
+        Ok(CheckOutcome::Upgraded)
     }
 }
 impl<T: PasswordAuth> PasswordAuthExt for T {}
@@ -0,0 +1,105 @@
+//  LDAP.rs
+//    by Lut99
+//
+//  Created:
+//    01 Feb 2024, 14:52:09
+//  Last edited:
+//    06 Feb 2024, 10:11:58
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a [`LoginProvider`] that delegates credential
+//!   verification to a remote LDAP directory through a simple bind.
+//
+
+use std::error;
+use std::fmt::{Display, Formatter, Result as FResult};
+
+use ldap3::LdapConnAsync;
+
+use crate::spec::{Credentials, LoginProvider};
+
+
+/***** ERRORS *****/
+/// Defines errors thrown by [`LdapProvider`].
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to connect to the configured LDAP server.
+    Connect { url: String, err: ldap3::LdapError },
+    /// The simple bind with the user's credentials failed (wrong password, no such DN, server rejected it, ...).
+    Bind { dn: String, err: ldap3::LdapError },
+    /// The given username/password-combination was rejected before even attempting a bind (e.g., an empty password, which LDAP servers treat as an anonymous bind and happily accept).
+    InvalidCredentials,
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            Connect { url, .. } => write!(f, "Failed to connect to LDAP server '{url}'"),
+            Bind { dn, .. } => write!(f, "Failed to bind as '{dn}'"),
+            InvalidCredentials => write!(f, "Invalid username or password"),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            Connect { err, .. } => Some(err),
+            Bind { err, .. } => Some(err),
+            InvalidCredentials => None,
+        }
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// A [`LoginProvider`] that authenticates a user by performing a simple bind against a remote LDAP directory.
+///
+/// This doesn't own the password store at all; the directory itself decides whether the username/password-combination is valid.
+#[derive(Clone, Debug)]
+pub struct LdapProvider {
+    /// The URL of the LDAP server to connect to (e.g. `ldap://directory.example.com:389`).
+    url: String,
+    /// A template for the user's bind DN, with the literal `{username}` replaced by the attempted username (e.g. `uid={username},ou=people,dc=example,dc=com`).
+    bind_dn_template: String,
+}
+impl LdapProvider {
+    /// Constructs a new [`LdapProvider`].
+    ///
+    /// # Arguments
+    /// - `url`: The URL of the LDAP server to connect to.
+    /// - `bind_dn_template`: A template for the user's bind DN, with the literal `{username}` replaced by the attempted username.
+    ///
+    /// # Returns
+    /// A new [`LdapProvider`].
+    #[inline]
+    pub fn new(url: impl Into<String>, bind_dn_template: impl Into<String>) -> Self { Self { url: url.into(), bind_dn_template: bind_dn_template.into() } }
+}
+impl LoginProvider for LdapProvider {
+    type Error = Error;
+
+    async fn login(&self, username: &str, password: &str) -> Result<Credentials, Self::Error> {
+        // Reject an empty (or whitespace-only) password upfront: most LDAP servers treat a
+        // simple bind with an empty password as an *unauthenticated* (anonymous) bind and
+        // report it as successful, regardless of whether the DN exists. Without this check,
+        // any known (or even guessed) DN would log in with a blank password.
+        if password.trim().is_empty() {
+            return Err(Error::InvalidCredentials);
+        }
+
+        // Connect to the directory
+        let (conn, mut ldap) = LdapConnAsync::new(&self.url).await.map_err(|err| Error::Connect { url: self.url.clone(), err })?;
+        ldap3::drive!(conn);
+
+        // Attempt the bind; the directory itself verifies the password
+        let dn: String = self.bind_dn_template.replace("{username}", username);
+        ldap.simple_bind(&dn, password).await.and_then(|res| res.success()).map_err(|err| Error::Bind { dn, err })?;
+
+        Ok(Credentials { username: username.into() })
+    }
+}
@@ -0,0 +1,124 @@
+//  MEMORY.rs
+//    by Lut99
+//
+//  Created:
+//    01 Feb 2024, 14:52:09
+//  Last edited:
+//    06 Feb 2024, 11:29:41
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a [`LoginProvider`] backed by an in-memory, mutable map
+//!   of username to password hash. Mostly useful for tests and demos.
+//
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter, Result as FResult};
+use std::sync::RwLock;
+
+use crate::password::{Error as PasswordError, PasswordAuthExt as _};
+use crate::providers::{check_entry, Entry};
+use crate::spec::{Credentials, LoginProvider};
+
+
+/***** ERRORS *****/
+/// Defines errors thrown by [`InMemoryProvider`].
+#[derive(Debug)]
+pub enum Error {
+    /// The lock guarding the internal user map was poisoned (i.e., a thread panicked while holding it).
+    LockPoisoned,
+    /// Failed to hash a newly inserted password.
+    PasswordHash { err: PasswordError },
+    /// Failed to check the given password attempt.
+    PasswordCheck { err: PasswordError },
+    /// The given username/password-combination was incorrect (or the user didn't exist at all).
+    InvalidCredentials,
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            LockPoisoned => write!(f, "Internal user map lock was poisoned"),
+            PasswordHash { err } => write!(f, "Failed to hash password: {err}"),
+            PasswordCheck { err } => write!(f, "Failed to check password: {err}"),
+            InvalidCredentials => write!(f, "Invalid username or password"),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            LockPoisoned => None,
+            PasswordHash { err } => Some(err),
+            PasswordCheck { err } => Some(err),
+            InvalidCredentials => None,
+        }
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// A [`LoginProvider`] backed by a mutable, in-memory map of username to PHC password hash.
+///
+/// Unlike [`StaticProvider`](crate::providers::static_provider::StaticProvider), users can be added & removed at runtime through [`Self::insert_user()`] and [`Self::remove_user()`]. Intended for tests and demos, not for production use (the map is never persisted).
+#[derive(Debug, Default)]
+pub struct InMemoryProvider {
+    /// Maps a username to its PHC password hash.
+    users: RwLock<HashMap<String, String>>,
+}
+impl InMemoryProvider {
+    /// Constructs a new, empty [`InMemoryProvider`].
+    ///
+    /// # Returns
+    /// A new [`InMemoryProvider`] with no users in it.
+    #[inline]
+    pub fn new() -> Self { Self::default() }
+
+    /// Inserts (or overwrites) a user, hashing their password before storing it.
+    ///
+    /// # Arguments
+    /// - `username`: The name of the user to insert.
+    /// - `password`: The cleartext password to hash and store for this user.
+    ///
+    /// # Errors
+    /// This function may error if hashing the password failed, or if the internal lock was poisoned.
+    pub fn insert_user(&self, username: impl Into<String>, password: impl AsRef<str>) -> Result<(), Error> {
+        let mut entry = Entry { hash: password.as_ref().into() };
+        entry.hash_password().map_err(|err| Error::PasswordHash { err })?;
+
+        let mut users = self.users.write().map_err(|_| Error::LockPoisoned)?;
+        users.insert(username.into(), entry.hash);
+        Ok(())
+    }
+
+    /// Removes a user, if they exist.
+    ///
+    /// # Arguments
+    /// - `username`: The name of the user to remove.
+    ///
+    /// # Errors
+    /// This function may error if the internal lock was poisoned.
+    pub fn remove_user(&self, username: &str) -> Result<(), Error> {
+        let mut users = self.users.write().map_err(|_| Error::LockPoisoned)?;
+        users.remove(username);
+        Ok(())
+    }
+}
+impl LoginProvider for InMemoryProvider {
+    type Error = Error;
+
+    async fn login(&self, username: &str, password: &str) -> Result<Credentials, Self::Error> {
+        let hash: Option<String> = self.users.read().map_err(|_| Error::LockPoisoned)?.get(username).cloned();
+        let ok: bool = check_entry(hash.as_deref(), password).map_err(|err| Error::PasswordCheck { err })?;
+        if !ok {
+            return Err(Error::InvalidCredentials);
+        }
+        Ok(Credentials { username: username.into() })
+    }
+}
@@ -0,0 +1,56 @@
+//  MOD.rs
+//    by Lut99
+//
+//  Created:
+//    01 Feb 2024, 14:52:09
+//  Last edited:
+//    01 Feb 2024, 14:52:09
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Provides concrete [`LoginProvider`](crate::spec::LoginProvider)
+//!   implementations.
+//
+
+// Declare submodules
+pub mod ldap;
+pub mod memory;
+pub mod static_provider;
+
+use crate::password::{Error as PasswordError, PasswordAuth, PasswordAuthExt as _};
+
+
+/***** HELPER FUNCTIONS *****/
+/// A minimal [`PasswordAuth`]-implementor wrapping a single already-hashed password.
+///
+/// This lets [`static_provider`] and [`memory`] reuse [`PasswordAuthExt::check_password_constant_time()`] without needing a full [`UserInfo`](crate::spec::UserInfo)-implementing type.
+pub(crate) struct Entry {
+    /// The PHC hash string of this entry's password.
+    hash: String,
+}
+impl PasswordAuth for Entry {
+    #[inline]
+    fn password(&self) -> &str { &self.hash }
+
+    #[inline]
+    fn password_mut(&mut self) -> &mut String { &mut self.hash }
+}
+
+/// Checks a password attempt against an optional stored PHC hash, in constant time w.r.t. whether the hash is present.
+///
+/// The dummy hash verified against when `stored` is [`None`] is computed with [`HashScheme::default()`](crate::password::HashScheme::default()), so the timing only matches for stored hashes produced by that same scheme.
+///
+/// # Arguments
+/// - `stored`: The PHC hash string to check against, or [`None`] if no user with the attempted name exists.
+/// - `attempt`: The cleartext password to verify.
+///
+/// # Returns
+/// True if `attempt` matches `stored`, or false otherwise (including when `stored` is [`None`]).
+///
+/// # Errors
+/// This function may error if it failed to parse or verify against the stored hash.
+pub(crate) fn check_entry(stored: Option<&str>, attempt: &str) -> Result<bool, PasswordError> {
+    let entry: Option<Entry> = stored.map(|hash| Entry { hash: hash.into() });
+    Entry::check_password_constant_time(entry.as_ref(), attempt.as_bytes())
+}
@@ -0,0 +1,87 @@
+//  STATIC PROVIDER.rs
+//    by Lut99
+//
+//  Created:
+//    01 Feb 2024, 14:52:09
+//  Last edited:
+//    01 Feb 2024, 14:52:09
+//  Auto updated?
+//    Yes
+//
+//  Description:
+//!   Implements a [`LoginProvider`] that authenticates against a fixed,
+//!   config-file-loaded map of username to password hash.
+//
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter, Result as FResult};
+
+use crate::password::Error as PasswordError;
+use crate::providers::check_entry;
+use crate::spec::{Credentials, LoginProvider};
+
+
+/***** ERRORS *****/
+/// Defines errors thrown by [`StaticProvider`].
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to check the given password attempt.
+    PasswordCheck { err: PasswordError },
+    /// The given username/password-combination was incorrect (or the user didn't exist at all).
+    InvalidCredentials,
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FResult {
+        use Error::*;
+        match self {
+            PasswordCheck { err } => write!(f, "Failed to check password: {err}"),
+            InvalidCredentials => write!(f, "Invalid username or password"),
+        }
+    }
+}
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        use Error::*;
+        match self {
+            PasswordCheck { err } => Some(err),
+            InvalidCredentials => None,
+        }
+    }
+}
+
+
+
+
+
+/***** LIBRARY *****/
+/// A [`LoginProvider`] that authenticates against a fixed map of username to PHC password hash.
+///
+/// Typically loaded once from a config file at startup; unlike [`InMemoryProvider`](crate::providers::memory::InMemoryProvider), it offers no way to add or remove users at runtime.
+#[derive(Clone, Debug, Default)]
+pub struct StaticProvider {
+    /// Maps a username to its PHC password hash.
+    users: HashMap<String, String>,
+}
+impl StaticProvider {
+    /// Constructs a new [`StaticProvider`] from a fixed map of username to PHC password hash.
+    ///
+    /// # Arguments
+    /// - `users`: The username -> PHC password hash map to authenticate against.
+    ///
+    /// # Returns
+    /// A new [`StaticProvider`].
+    #[inline]
+    pub fn new(users: HashMap<String, String>) -> Self { Self { users } }
+}
+impl LoginProvider for StaticProvider {
+    type Error = Error;
+
+    async fn login(&self, username: &str, password: &str) -> Result<Credentials, Self::Error> {
+        let ok: bool = check_entry(self.users.get(username).map(String::as_str), password).map_err(|err| Error::PasswordCheck { err })?;
+        if !ok {
+            return Err(Error::InvalidCredentials);
+        }
+        Ok(Credentials { username: username.into() })
+    }
+}
@@ -4,7 +4,7 @@
 //  Created:
 //    02 Jan 2024, 13:40:11
 //  Last edited:
-//    02 Jan 2024, 14:16:57
+//    06 Feb 2024, 11:18:40
 //  Auto updated?
 //    Yes
 //
@@ -14,10 +14,25 @@
 
 use std::error::Error;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::password::HashScheme;
+
 
 /***** LIBRARY *****/
+/// A generic reply sent to the client whenever a handler in this crate fails.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ErrorReply {
+    /// A machine-readable identifier for the kind of error that occurred.
+    pub id:      String,
+    /// A human-readable message describing what went wrong.
+    pub message: String,
+}
+
+
+
+
 /// Defines an object that, partly, contains authentication context to handle requests.
 /// 
 /// # Generics
@@ -28,6 +43,15 @@ pub trait AuthContext<U> {
     /// # Returns
     /// A reference to a type implementing [`AuthConnector`].
     fn auth_connector(&self) -> &impl AuthConnector<U>;
+
+    /// Returns the [`HashScheme`] (algorithm + cost parameters) to hash and verify passwords with in this context.
+    ///
+    /// Defaults to [`HashScheme::default()`]. Override this if the deployment tunes cost parameters or hashes with a non-default algorithm; both [`login()`](crate::login::login) and [`create()`](crate::manage::create) read this rather than assuming the bare defaults, so overriding it here is enough to change it everywhere — including on the "user does not exist" dummy-hash path, which must match real users' scheme to stay constant-time.
+    ///
+    /// # Returns
+    /// The [`HashScheme`] to use for this context.
+    #[inline]
+    fn hash_scheme(&self) -> HashScheme { HashScheme::default() }
 }
 
 
@@ -38,7 +62,9 @@ pub trait AuthContext<U> {
 /// - `U`: A struct that carries all information we might like to know of a user.
 pub trait AuthConnector<U> {
     /// Errors to throw for this connector.
-    type Error: Error;
+    ///
+    /// Bounded by `'static` (not just [`Error`]) because callers (e.g. [`login()`](crate::login::login), [`create()`](crate::manage::create)) unsize it into a `Box<dyn Error + 'static>` to report it alongside other error sources; unsizing a generic `E: Error` into that trait object requires `E: 'static`.
+    type Error: Error + 'static;
 
 
     // Read-only methods
@@ -54,18 +80,124 @@ pub trait AuthConnector<U> {
     /// This function may error if we failed to do the database stuff.
     fn user_exists(&self, name: &str) -> Result<bool, Self::Error>;
 
+    /// Fetches the information of an existing user.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the user to fetch.
+    ///
+    /// # Returns
+    /// The [`UserInfo`]-information of the user with this name, or [`None`] if no such user exists.
+    ///
+    /// # Errors
+    /// This function may error if we failed to do the database stuff.
+    fn fetch_user(&self, name: &str) -> Result<Option<U>, Self::Error>;
+
+    /// Fetches all credentials on file for a given user.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the user to fetch the credentials of.
+    ///
+    /// # Returns
+    /// A list of [`Credential`]s on file for this user. Empty (not an error) if the user has none, or doesn't exist.
+    ///
+    /// # Errors
+    /// This function may error if we failed to do the database stuff.
+    fn fetch_credentials(&self, name: &str) -> Result<Vec<Credential>, Self::Error>;
+
 
     // Write methods
     /// Inserts a new user into the database.
-    /// 
+    ///
     /// Note that a check for user uniqueness has already occurred (though it can never hurt to do it twice).
-    /// 
+    ///
     /// # Arguments
     /// - `info`: A type that should be written to the database for this user.
-    /// 
+    ///
     /// # Errors
     /// This function may error if it failed to do the database stuff or if it suspects foul play for some reason.
-    fn insert_user(&self, info: U)
+    fn insert_user(&self, info: U) -> Result<(), Self::Error>;
+
+    /// Inserts (or overwrites) a single credential for an existing user.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the user to insert the credential for.
+    /// - `credential`: The [`Credential`] to write, keyed by its [`CredentialType`] (i.e., a user has at most one credential per type).
+    ///
+    /// # Errors
+    /// This function may error if it failed to do the database stuff.
+    fn insert_credential(&self, name: &str, credential: Credential) -> Result<(), Self::Error>;
+}
+
+
+
+/// Distinguishes the kinds of credentials a user may have on file.
+///
+/// Mirrors the `credential_type` column of a `credential` table keyed by `(user_id, credential_type)`: a user has at most one [`Credential`] of each [`CredentialType`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum CredentialType {
+    /// A hashed password, as used by the local [`AuthConnector`]-backed login path.
+    Password,
+    /// An API token.
+    ApiToken,
+    /// An OAuth-issued credential. Reserved for future use.
+    OAuth,
+}
+
+/// A single credential on file for a user, mirroring a row in a `credential` table keyed by `(user_id, credential_type)`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Credential {
+    /// Which kind of credential this is.
+    pub kind:         CredentialType,
+    /// The credential's value (e.g., a PHC password hash, or a hashed API token).
+    pub value:        String,
+    /// Whether this credential has been validated (e.g., the user proved ownership of the associated email before a password credential is accepted for login).
+    pub validated:    bool,
+    /// When this credential was first created.
+    pub time_created: DateTime<Utc>,
+    /// When this credential was last updated.
+    pub last_updated: DateTime<Utc>,
+}
+
+
+
+/// Credentials returned by a [`LoginProvider`] upon successful authentication.
+///
+/// This is deliberately opaque to which backend actually authenticated the user (a local database, an LDAP directory, ...), so callers can act on a single uniform type regardless of which [`LoginProvider`] is configured.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Credentials {
+    /// The name of the user that was authenticated.
+    pub username: String,
+}
+
+
+
+/// Defines a backend that can authenticate a username/password-combination.
+///
+/// This complements [`AuthConnector`], which models a database this crate owns and manages the password hashes of. A [`LoginProvider`] instead only needs to say yes or no to a login attempt, which also makes it suitable for backends that don't own the password store themselves (e.g., an LDAP directory that does its own bind).
+pub trait LoginProvider {
+    /// Errors thrown by this provider.
+    ///
+    /// Bounded by `'static` for the same reason as [`AuthConnector::Error`]: implementors may need to unsize it into a `Box<dyn Error + 'static>`.
+    type Error: Error + 'static;
+
+    /// Attempts to log a user in with the given username/password-combination.
+    ///
+    /// # Arguments
+    /// - `username`: The name of the user that attempts to log in.
+    /// - `password`: The cleartext password to authenticate with.
+    ///
+    /// # Returns
+    /// The [`Credentials`] of the now-authenticated user.
+    ///
+    /// # Errors
+    /// This function may error if the backend could not be reached, or if the given username/password-combination was invalid.
+    // `async fn` in a public trait warns under `async_fn_in_trait` (the resulting future isn't
+    // guaranteed `Send`, which would stop it being spawned by most async runtimes). We allow it
+    // deliberately here: all current implementors (`StaticProvider`, `InMemoryProvider`,
+    // `LdapProvider`) only ever hold `Send` state across their awaits, so their futures are in
+    // practice `Send`; revisit if a future implementor breaks that.
+    #[allow(async_fn_in_trait)]
+    async fn login(&self, username: &str, password: &str) -> Result<Credentials, Self::Error>;
 }
 
 
@@ -78,19 +210,22 @@ pub trait UserInfo<'de>: Deserialize<'de> + Serialize {
     /// A reference to the user's name.
     fn name(&self) -> &str;
 
-    /// Returns the password of this user, as stored in the database.
+    /// Returns the credential of the given kind on file for this user, if any.
+    ///
+    /// For a [`CredentialType::Password`], this should return what has been set by [`Self::set_credential()`] (e.g., the hashed password, once the authentication scheme has taken care of hashing it).
     ///
-    /// Note that the authentication scheme takes care of hashing, so this should return what has been set by [`Self::update_password()`](UserInfo::update_password()).
+    /// # Arguments
+    /// - `kind`: The [`CredentialType`] of the credential to return.
     ///
     /// # Returns
-    /// A reference to the hashed password.
-    fn password(&self) -> &str;
+    /// A reference to the matching [`Credential`], or [`None`] if this user has none of that kind.
+    fn credential(&self, kind: CredentialType) -> Option<&Credential>;
 
-    /// Sets a new password for this user.
+    /// Sets (inserts or overwrites) a credential for this user.
     ///
-    /// This is used when handling new users to hash their passwords before storage, or when users update their password.
+    /// This is used when handling new users to replace a cleartext password with its hashed counterpart before storage, or whenever a user's credential of a given [`CredentialType`] changes.
     ///
     /// # Arguments
-    /// - `password`: The new password to set internally.
-    fn update_password(&mut self, password: String);
+    /// - `credential`: The new [`Credential`] to set internally, keyed by its [`CredentialType`].
+    fn set_credential(&mut self, credential: Credential);
 }